@@ -0,0 +1,365 @@
+//! Pluggable mechanisms for disabling interrupts.
+//!
+//! [`InterruptMutex`](crate::InterruptMutex) needs a way to disable whatever "interrupts" mean
+//! for the target environment: the CPU interrupt flag on bare metal, or a signal mask on a
+//! hosted, POSIX platform. [`InterruptBackend`] abstracts over that choice, the same way the
+//! `embassy` `blocking_mutex` family is generic over a `RawMutex` to pick the right
+//! critical-section strategy per environment.
+
+use core::cell::UnsafeCell;
+use core::mem::MaybeUninit;
+use core::sync::atomic::{fence, AtomicUsize, Ordering};
+
+#[cfg(feature = "smp")]
+compile_error!(
+    "the `smp` feature is reserved for future per-core `Nesting` support and is not implemented \
+     yet; `DefaultBackend::with_nesting` in this crate hands out one process-wide static, which \
+     is only sound when a backend is used from a single core at a time"
+);
+
+/// A mechanism for disabling and restoring interrupts (or whatever stands in for them on the
+/// target environment, such as a signal mask).
+///
+/// Implementations must uphold the same contract as [`interrupts::disable`]: interrupts are
+/// disabled for as long as the returned [`Guard`](InterruptBackend::Guard) is alive, and are
+/// restored to their previous state when it is dropped.
+pub trait InterruptBackend {
+    /// A guard that restores the previous interrupt state when dropped.
+    type Guard: 'static;
+
+    /// Disables interrupts and returns a guard that restores the previous state when dropped.
+    fn disable() -> Self::Guard;
+
+    /// Gives `f` access to the [`Nesting`] state shared by every interrupt-mutex or
+    /// interrupt-rwlock using this backend.
+    ///
+    /// This lets interrupts stay disabled for the entire span in which *any* of them is held,
+    /// regardless of the order in which their guards are dropped. Implementations choose how a
+    /// `Nesting` is scoped, and must scope it to exactly the execution contexts that share the
+    /// effect of one `disable()` call: see the warning on [`Nesting`]. For example,
+    /// [`DefaultBackend`] hands out one process-wide `static` `Nesting`, while
+    /// [`signal_backend!`](crate::signal_backend) hands out a `std::thread_local!` one, because a
+    /// signal mask set by `disable` only ever takes effect on the calling thread. This is the
+    /// extension point for writing a custom [`InterruptBackend`] by hand.
+    fn with_nesting<R>(f: impl FnOnce(&Nesting<Self::Guard>) -> R) -> R;
+}
+
+/// Tracks how many interrupt-mutexes sharing a backend are currently held, so that interrupts are
+/// only disabled once (by the first one) and only restored once (by the last one).
+///
+/// This mirrors the Linux `spin_lock_irqsave`/`spin_unlock_irqrestore` nesting model: rather than
+/// each lock independently saving and restoring interrupt state, a single saved state is shared by
+/// every nested critical section, so release order doesn't matter.
+///
+/// This is the building block [`InterruptBackend::with_nesting`] hands a reference to. Writing a
+/// backend by hand, rather than via [`signal_backend!`](crate::signal_backend), means storing a
+/// `Nesting` somewhere `with_nesting` can reach: a `static` if `disable` affects every execution
+/// context that could call it, or a `std::thread_local!` one if `disable` only affects the calling
+/// thread.
+///
+/// <div class="warning">
+///
+/// A single `Nesting` must only ever be entered by execution contexts that share the effect of
+/// one `disable()` call. Sharing one `Nesting` across contexts where `disable` does *not* have
+/// that shared effect is unsound: a context that observes `depth != 0` skips calling `disable`,
+/// even though its own interrupts (or signal mask) were never touched. Concretely:
+///
+/// - [`DefaultBackend`] hands out a single, process-wide `Nesting`, which is only sound on targets
+///   with a single execution context sharing that backend: if one core holds an interrupt-mutex
+///   (`depth != 0`) and a *different* core locks another interrupt-mutex on the same backend, that
+///   core's `enter` wrongly observes interrupts as already disabled. Per-core `Nesting` storage is
+///   needed to use [`DefaultBackend`] from more than one core at a time; the `smp` feature is
+///   reserved for this and is not implemented yet.
+/// - [`signal_backend!`](crate::signal_backend) avoids this for its own case by giving each OS
+///   thread its own `Nesting` via `std::thread_local!`, since a blocked signal mask is only ever in
+///   effect on the thread that blocked it.
+///
+/// </div>
+pub struct Nesting<G> {
+    depth: AtomicUsize,
+    guard: UnsafeCell<MaybeUninit<G>>,
+}
+
+// SAFETY: `guard` is only written by the call that takes `depth` from 0 to 1, and only read by the
+// call that takes `depth` from 1 to 0; `depth` itself is only ever touched through atomic ops.
+unsafe impl<G> Sync for Nesting<G> {}
+
+impl<G> Default for Nesting<G> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<G> Nesting<G> {
+    /// Creates a new, not-currently-held nesting state.
+    pub const fn new() -> Self {
+        Self {
+            depth: AtomicUsize::new(0),
+            guard: UnsafeCell::new(MaybeUninit::uninit()),
+        }
+    }
+
+    /// Enters a nested critical section, disabling interrupts via `B::disable` if none of this
+    /// backend's critical sections are currently entered.
+    pub(crate) fn enter<B: InterruptBackend<Guard = G>>(&self) {
+        if self.depth.fetch_add(1, Ordering::Relaxed) == 0 {
+            let guard = B::disable();
+            // SAFETY: We are the call that took `depth` from 0 to 1.
+            unsafe {
+                self.guard.get().write(MaybeUninit::new(guard));
+            }
+            // Pairs with the acquire fence in `exit`: makes sure the write above happens-before
+            // whichever call later takes `depth` back down to 0 reads it.
+            fence(Ordering::Release);
+        }
+    }
+
+    /// Exits a nested critical section, restoring interrupts once every entry has exited.
+    pub(crate) fn exit(&self) {
+        if self.depth.fetch_sub(1, Ordering::Relaxed) == 1 {
+            // Pairs with the release fence in `enter`.
+            fence(Ordering::Acquire);
+            // SAFETY: We are the call that took `depth` from 1 to 0.
+            let guard = unsafe { self.guard.get().replace(MaybeUninit::uninit()) };
+            // SAFETY: `guard` was initialized by the call that took `depth` from 0 to 1.
+            let guard = unsafe { guard.assume_init() };
+            drop(guard);
+        }
+    }
+}
+
+/// The default [`InterruptBackend`], backed by the [`interrupts`] crate.
+///
+/// This is the backend used when no other is specified, matching the crate's original,
+/// hardware-interrupt-oriented behavior.
+pub struct DefaultBackend;
+
+impl InterruptBackend for DefaultBackend {
+    type Guard = interrupts::Guard;
+
+    #[inline]
+    fn disable() -> Self::Guard {
+        interrupts::disable()
+    }
+
+    fn with_nesting<R>(f: impl FnOnce(&Nesting<Self::Guard>) -> R) -> R {
+        static NESTING: Nesting<interrupts::Guard> = Nesting::new();
+        f(&NESTING)
+    }
+}
+
+#[cfg(feature = "signal")]
+mod signal {
+    use nix::sys::signal::{SigSet, SigmaskHow};
+
+    /// A guard that restores the previous signal mask when dropped.
+    ///
+    /// Returned by [`InterruptBackend::disable`](super::InterruptBackend::disable) for backends
+    /// defined with [`signal_backend`](crate::signal_backend).
+    pub struct SignalGuard {
+        old_set: SigSet,
+    }
+
+    impl SignalGuard {
+        /// Blocks `set` for the current thread, returning a guard that restores the previous mask.
+        pub fn block(set: SigSet) -> Self {
+            let old_set = set.block_saving_old().expect("failed to block signals");
+            Self { old_set }
+        }
+    }
+
+    impl Drop for SignalGuard {
+        fn drop(&mut self) {
+            self.old_set
+                .set_as_thread_mask()
+                .expect("failed to restore signal mask");
+        }
+    }
+
+    // `SigSet::thread_block`/`thread_set_mask` don't return the previous mask, which we need to
+    // restore it later, so go through `pthread_sigmask` directly instead.
+    trait SigSetExt {
+        fn block_saving_old(&self) -> nix::Result<SigSet>;
+        fn set_as_thread_mask(&self) -> nix::Result<()>;
+    }
+
+    impl SigSetExt for SigSet {
+        fn block_saving_old(&self) -> nix::Result<SigSet> {
+            let mut old_set = SigSet::empty();
+            nix::sys::signal::pthread_sigmask(
+                SigmaskHow::SIG_BLOCK,
+                Some(self),
+                Some(&mut old_set),
+            )?;
+            Ok(old_set)
+        }
+
+        fn set_as_thread_mask(&self) -> nix::Result<()> {
+            nix::sys::signal::pthread_sigmask(SigmaskHow::SIG_SETMASK, Some(self), None)
+        }
+    }
+}
+
+#[cfg(feature = "signal")]
+pub use signal::SignalGuard;
+
+/// Defines an [`InterruptBackend`] that disables interrupts by blocking a fixed set of POSIX
+/// signals for the current thread, restoring the previous signal mask when the guard is dropped.
+///
+/// This makes masking a configurable signal set (for example, `SIGINT` on a hosted platform) a
+/// first-class backend instead of demo-only signal-handling glue.
+///
+/// # Examples
+///
+/// ```
+/// # #[cfg(feature = "signal")]
+/// # {
+/// interrupt_mutex::signal_backend!(pub SigintBackend, [nix::sys::signal::Signal::SIGINT]);
+///
+/// type InterruptMutex<T> = interrupt_mutex::InterruptMutex<parking_lot::RawMutex, T, SigintBackend>;
+/// # }
+/// ```
+#[cfg(feature = "signal")]
+#[macro_export]
+macro_rules! signal_backend {
+    ($vis:vis $name:ident, [$($signal:expr),+ $(,)?]) => {
+        $vis struct $name;
+
+        impl $crate::InterruptBackend for $name {
+            type Guard = $crate::SignalGuard;
+
+            fn disable() -> Self::Guard {
+                let mut set = ::nix::sys::signal::SigSet::empty();
+                $( set.add($signal); )+
+                $crate::SignalGuard::block(set)
+            }
+
+            fn with_nesting<R>(f: impl FnOnce(&$crate::Nesting<Self::Guard>) -> R) -> R {
+                // Signal masks are per-thread, so each thread needs its own `Nesting`: sharing one
+                // across threads would let a thread observe `depth != 0` and skip `disable`
+                // entirely, even though its own signal mask was never touched.
+                ::std::thread_local! {
+                    static NESTING: $crate::Nesting<$crate::SignalGuard> = $crate::Nesting::new();
+                }
+                NESTING.with(f)
+            }
+        }
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use core::sync::atomic::AtomicBool;
+
+    use super::*;
+
+    struct CountingGuard<'a>(&'a AtomicBool);
+
+    impl Drop for CountingGuard<'_> {
+        fn drop(&mut self) {
+            self.0.store(true, Ordering::SeqCst);
+        }
+    }
+
+    #[test]
+    fn enter_disables_once_and_exit_restores_once() {
+        static ENABLED: AtomicBool = AtomicBool::new(true);
+        static DISABLE_CALLS: AtomicUsize = AtomicUsize::new(0);
+        static NESTING: Nesting<CountingGuard<'static>> = Nesting::new();
+
+        struct TestBackend;
+
+        impl InterruptBackend for TestBackend {
+            type Guard = CountingGuard<'static>;
+
+            fn disable() -> Self::Guard {
+                DISABLE_CALLS.fetch_add(1, Ordering::SeqCst);
+                ENABLED.store(false, Ordering::SeqCst);
+                CountingGuard(&ENABLED)
+            }
+
+            fn with_nesting<R>(f: impl FnOnce(&Nesting<Self::Guard>) -> R) -> R {
+                f(&NESTING)
+            }
+        }
+
+        assert!(ENABLED.load(Ordering::SeqCst));
+
+        NESTING.enter::<TestBackend>();
+        assert!(!ENABLED.load(Ordering::SeqCst));
+        assert_eq!(DISABLE_CALLS.load(Ordering::SeqCst), 1);
+
+        // Nested entry must not disable again or be observable as a second `disable()` call.
+        NESTING.enter::<TestBackend>();
+        assert!(!ENABLED.load(Ordering::SeqCst));
+        assert_eq!(DISABLE_CALLS.load(Ordering::SeqCst), 1);
+
+        NESTING.exit();
+        assert!(!ENABLED.load(Ordering::SeqCst));
+
+        NESTING.exit();
+        assert!(ENABLED.load(Ordering::SeqCst));
+        assert_eq!(DISABLE_CALLS.load(Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn with_nesting_is_independent_per_thread_local_instance() {
+        // Mirrors how `signal_backend!` scopes `Nesting` per OS thread: two independently-created
+        // thread-local `Nesting`s (standing in for two different threads) must not observe each
+        // other's depth.
+        struct LocalGuard<'a>(&'a AtomicBool);
+
+        impl Drop for LocalGuard<'_> {
+            fn drop(&mut self) {
+                self.0.store(true, Ordering::SeqCst);
+            }
+        }
+
+        static ENABLED_A: AtomicBool = AtomicBool::new(true);
+        static ENABLED_B: AtomicBool = AtomicBool::new(true);
+        static NESTING_A: Nesting<LocalGuard<'static>> = Nesting::new();
+        static NESTING_B: Nesting<LocalGuard<'static>> = Nesting::new();
+
+        struct BackendA;
+        struct BackendB;
+
+        impl InterruptBackend for BackendA {
+            type Guard = LocalGuard<'static>;
+
+            fn disable() -> Self::Guard {
+                ENABLED_A.store(false, Ordering::SeqCst);
+                LocalGuard(&ENABLED_A)
+            }
+
+            fn with_nesting<R>(f: impl FnOnce(&Nesting<Self::Guard>) -> R) -> R {
+                f(&NESTING_A)
+            }
+        }
+
+        impl InterruptBackend for BackendB {
+            type Guard = LocalGuard<'static>;
+
+            fn disable() -> Self::Guard {
+                ENABLED_B.store(false, Ordering::SeqCst);
+                LocalGuard(&ENABLED_B)
+            }
+
+            fn with_nesting<R>(f: impl FnOnce(&Nesting<Self::Guard>) -> R) -> R {
+                f(&NESTING_B)
+            }
+        }
+
+        NESTING_A.enter::<BackendA>();
+        assert!(!ENABLED_A.load(Ordering::SeqCst));
+        // A separate `Nesting` must not see `NESTING_A`'s depth and must still disable on entry.
+        assert!(ENABLED_B.load(Ordering::SeqCst));
+        NESTING_B.enter::<BackendB>();
+        assert!(!ENABLED_B.load(Ordering::SeqCst));
+
+        NESTING_A.exit();
+        assert!(ENABLED_A.load(Ordering::SeqCst));
+        assert!(!ENABLED_B.load(Ordering::SeqCst));
+        NESTING_B.exit();
+        assert!(ENABLED_B.load(Ordering::SeqCst));
+    }
+}
@@ -7,6 +7,18 @@
 //! When the mutex is unlocked, the previous interrupt state is restored.
 //! This makes [`InterruptMutex`] suitable for sharing data with interrupts.
 //!
+//! [`InterruptRwLock`] does the same for reader-writer locks, for data that is mostly read from
+//! interrupt handlers and only rarely updated.
+//!
+//! The `poison` feature additionally provides [`PoisonInterruptMutex`], which poisons itself if a
+//! panic occurs while a guard is held, the same way [`std::sync::Mutex`] does.
+//!
+//! What "disabling interrupts" means depends on the target environment: hardware interrupts on
+//! bare metal, or POSIX signals on a hosted platform. [`InterruptBackend`] abstracts over this;
+//! [`DefaultBackend`] (used unless another backend is specified) disables hardware interrupts via
+//! the [`interrupts`] crate, and [`signal_backend!`] defines backends that mask a configurable set
+//! of signals instead.
+//!
 //! When used in bare-metal environments with spinlocks, locking the mutex corresponds to Linux's `spin_lock_irqsave` and unlocking corresponds to `spin_unlock_irqrestore`.
 //! See the [Unreliable Guide To Locking — The Linux Kernel documentation].
 //! While `spin_lock_irqsave(lock, flags)` saves the interrupt flags in the explicit `flags` argument, [`InterruptMutex`] saves the interrupt flags internally.
@@ -19,10 +31,17 @@
 //!
 //! <div class="warning">Interrupts are disabled on a best-effort basis.</div>
 //!
-//! Holding an [`InterruptMutexGuard`] does not guarantee that interrupts are disabled.
-//! Dropping guards from different [`InterruptMutex`]es in the wrong order might enable interrupts prematurely.
+//! Holding an [`InterruptMutexGuard`] or [`InterruptRwLock`] guard does not guarantee that interrupts are disabled.
+//! [`InterruptMutex`]es and [`InterruptRwLock`]s sharing a backend nest like `spin_lock_irqsave`/`spin_unlock_irqrestore`, so dropping their guards in any order is safe; mixing backends is not covered by this and can still re-enable interrupts prematurely.
 //! Similarly, you can just enable interrupts manually while holding a guard.
 //!
+//! <div class="warning">[`DefaultBackend`]'s nesting is only safe on a single core.</div>
+//!
+//! [`DefaultBackend`] shares one process-wide nesting state.
+//! If one core holds an [`InterruptMutex`] and a *different* core locks another [`InterruptMutex`] on the same backend, the second core will wrongly see interrupts as already disabled and will not disable its own.
+//! Do not use [`DefaultBackend`] from more than one core at a time until per-core nesting state is implemented.
+//! [`signal_backend!`] backends do not have this problem: each OS thread gets its own nesting state, since a signal mask is only ever in effect on the thread that set it.
+//!
 //! # Examples
 //!
 //! ```
@@ -64,78 +83,26 @@
 //! drop(v);
 //! ```
 
-#![no_std]
-
-use core::cell::UnsafeCell;
-use core::mem::MaybeUninit;
-
-use lock_api::{GuardNoSend, RawMutex};
-
-/// A mutex for sharing data with interrupt handlers or signal handlers.
-///
-/// This mutex wraps another [`RawMutex`] and disables interrupts while locked.
-pub struct RawInterruptMutex<I> {
-    inner: I,
-    interrupt_guard: UnsafeCell<MaybeUninit<interrupts::Guard>>,
-}
-
-// SAFETY: The `UnsafeCell` is locked by `inner`, initialized on `lock` and uninitialized on `unlock`.
-unsafe impl<I: Sync> Sync for RawInterruptMutex<I> {}
-// SAFETY: Mutexes cannot be send to other threads while locked.
-// Sending them while unlocked is fine.
-unsafe impl<I: Send> Send for RawInterruptMutex<I> {}
-
-unsafe impl<I: RawMutex> RawMutex for RawInterruptMutex<I> {
-    const INIT: Self = Self {
-        inner: I::INIT,
-        interrupt_guard: UnsafeCell::new(MaybeUninit::uninit()),
-    };
-
-    type GuardMarker = GuardNoSend;
-
-    #[inline]
-    fn lock(&self) {
-        let guard = interrupts::disable();
-        self.inner.lock();
-        // SAFETY: We have exclusive access through locking `inner`.
-        unsafe {
-            self.interrupt_guard.get().write(MaybeUninit::new(guard));
-        }
-    }
-
-    #[inline]
-    fn try_lock(&self) -> bool {
-        let guard = interrupts::disable();
-        let ok = self.inner.try_lock();
-        if ok {
-            // SAFETY: We have exclusive access through locking `inner`.
-            unsafe {
-                self.interrupt_guard.get().write(MaybeUninit::new(guard));
-            }
-        }
-        ok
-    }
+#![cfg_attr(not(feature = "poison"), no_std)]
 
-    #[inline]
-    unsafe fn unlock(&self) {
-        // SAFETY: We have exclusive access through locking `inner`.
-        let guard = unsafe { self.interrupt_guard.get().replace(MaybeUninit::uninit()) };
-        // SAFETY: `guard` was initialized when locking.
-        let guard = unsafe { guard.assume_init() };
-        unsafe {
-            self.inner.unlock();
-        }
-        drop(guard);
-    }
+// `signal_backend!` needs `std::thread_local!` to give each OS thread its own `Nesting`, which
+// isn't available through the `no_std` prelude.
+#[cfg(feature = "signal")]
+extern crate std;
 
-    #[inline]
-    fn is_locked(&self) -> bool {
-        self.inner.is_locked()
-    }
-}
+mod backend;
+mod mutex;
+#[cfg(feature = "poison")]
+mod poison;
+mod rwlock;
 
-/// A [`lock_api::Mutex`] based on [`RawInterruptMutex`].
-pub type InterruptMutex<I, T> = lock_api::Mutex<RawInterruptMutex<I>, T>;
+pub use backend::{DefaultBackend, InterruptBackend, Nesting};
+pub use mutex::{InterruptMutex, InterruptMutexGuard, RawInterruptMutex};
+#[cfg(feature = "poison")]
+pub use poison::{PoisonInterruptMutex, PoisonInterruptMutexGuard};
+pub use rwlock::{
+    InterruptRwLock, InterruptRwLockReadGuard, InterruptRwLockWriteGuard, RawInterruptRwLock,
+};
 
-/// A [`lock_api::MutexGuard`] based on [`RawInterruptMutex`].
-pub type InterruptMutexGuard<'a, I, T> = lock_api::MutexGuard<'a, RawInterruptMutex<I>, T>;
+#[cfg(feature = "signal")]
+pub use backend::SignalGuard;
@@ -0,0 +1,215 @@
+use core::marker::PhantomData;
+
+use lock_api::{GuardNoSend, RawMutex, RawMutexFair, RawMutexTimed};
+
+use crate::backend::{DefaultBackend, InterruptBackend};
+
+/// A mutex for sharing data with interrupt handlers or signal handlers.
+///
+/// This mutex wraps another [`RawMutex`] and disables interrupts while locked, using `B` to
+/// decide what disabling and restoring interrupts means on the target environment.
+/// [`DefaultBackend`] is used when `B` is left unspecified, disabling interrupts via the
+/// [`interrupts`] crate.
+///
+/// Interrupts are disabled for as long as *any* `RawInterruptMutex<_, B>` using the same backend
+/// `B` is held, not just this one: see [`InterruptBackend::with_nesting`]. This means the order in
+/// which guards of different interrupt-mutexes are dropped does not matter, as long as they share
+/// a backend.
+pub struct RawInterruptMutex<I, B = DefaultBackend> {
+    inner: I,
+    _backend: PhantomData<B>,
+}
+
+// SAFETY: Interrupt state is tracked in the backend's `Nesting` (see
+// `InterruptBackend::with_nesting`), not in `self`.
+unsafe impl<I: Sync, B> Sync for RawInterruptMutex<I, B> {}
+// SAFETY: Mutexes cannot be send to other threads while locked.
+// Sending them while unlocked is fine.
+unsafe impl<I: Send, B> Send for RawInterruptMutex<I, B> {}
+
+unsafe impl<I: RawMutex, B: InterruptBackend> RawMutex for RawInterruptMutex<I, B> {
+    const INIT: Self = Self {
+        inner: I::INIT,
+        _backend: PhantomData,
+    };
+
+    type GuardMarker = GuardNoSend;
+
+    #[inline]
+    fn lock(&self) {
+        B::with_nesting(|n| n.enter::<B>());
+        self.inner.lock();
+    }
+
+    #[inline]
+    fn try_lock(&self) -> bool {
+        B::with_nesting(|n| n.enter::<B>());
+        let ok = self.inner.try_lock();
+        if !ok {
+            B::with_nesting(|n| n.exit());
+        }
+        ok
+    }
+
+    #[inline]
+    unsafe fn unlock(&self) {
+        unsafe {
+            self.inner.unlock();
+        }
+        B::with_nesting(|n| n.exit());
+    }
+
+    #[inline]
+    fn is_locked(&self) -> bool {
+        self.inner.is_locked()
+    }
+}
+
+// SAFETY: Forwards to `inner`, which upholds the `RawMutexFair` contract; the nesting state is
+// only exited once the fair unlock has handed off the inner lock.
+unsafe impl<I: RawMutexFair, B: InterruptBackend> RawMutexFair for RawInterruptMutex<I, B> {
+    #[inline]
+    unsafe fn unlock_fair(&self) {
+        unsafe {
+            self.inner.unlock_fair();
+        }
+        B::with_nesting(|n| n.exit());
+    }
+
+    #[inline]
+    unsafe fn bump(&self) {
+        // The lock is not released, so interrupts stay disabled throughout.
+        unsafe {
+            self.inner.bump();
+        }
+    }
+}
+
+// SAFETY: Forwards to `inner`, which upholds the `RawMutexTimed` contract; the nesting state is
+// only entered once the timed attempt has actually acquired the inner lock.
+unsafe impl<I: RawMutexTimed, B: InterruptBackend> RawMutexTimed for RawInterruptMutex<I, B> {
+    type Duration = I::Duration;
+    type Instant = I::Instant;
+
+    #[inline]
+    fn try_lock_for(&self, timeout: Self::Duration) -> bool {
+        // Unlike `lock`/`try_lock`, interrupts must stay enabled while waiting: the wait can take
+        // up to `timeout`, and disabling interrupts for that long would defeat the point of a
+        // bounded wait. Only disable them once the inner lock is actually held.
+        let ok = self.inner.try_lock_for(timeout);
+        if ok {
+            B::with_nesting(|n| n.enter::<B>());
+        }
+        ok
+    }
+
+    #[inline]
+    fn try_lock_until(&self, timeout: Self::Instant) -> bool {
+        // See `try_lock_for`.
+        let ok = self.inner.try_lock_until(timeout);
+        if ok {
+            B::with_nesting(|n| n.enter::<B>());
+        }
+        ok
+    }
+}
+
+/// A [`lock_api::Mutex`] based on [`RawInterruptMutex`].
+pub type InterruptMutex<I, T, B = DefaultBackend> = lock_api::Mutex<RawInterruptMutex<I, B>, T>;
+
+/// A [`lock_api::MutexGuard`] based on [`RawInterruptMutex`].
+pub type InterruptMutexGuard<'a, I, T, B = DefaultBackend> =
+    lock_api::MutexGuard<'a, RawInterruptMutex<I, B>, T>;
+
+#[cfg(test)]
+mod tests {
+    use core::sync::atomic::{AtomicBool, Ordering};
+
+    use super::*;
+    use crate::backend::Nesting;
+
+    static ENABLED: AtomicBool = AtomicBool::new(true);
+
+    struct TestBackend;
+
+    struct TestGuard;
+
+    impl Drop for TestGuard {
+        fn drop(&mut self) {
+            ENABLED.store(true, Ordering::SeqCst);
+        }
+    }
+
+    impl InterruptBackend for TestBackend {
+        type Guard = TestGuard;
+
+        fn disable() -> Self::Guard {
+            ENABLED.store(false, Ordering::SeqCst);
+            TestGuard
+        }
+
+        fn with_nesting<R>(f: impl FnOnce(&Nesting<Self::Guard>) -> R) -> R {
+            static NESTING: Nesting<TestGuard> = Nesting::new();
+            f(&NESTING)
+        }
+    }
+
+    struct FakeMutex(AtomicBool);
+
+    unsafe impl RawMutex for FakeMutex {
+        const INIT: Self = Self(AtomicBool::new(false));
+
+        type GuardMarker = GuardNoSend;
+
+        fn lock(&self) {
+            while self.0.swap(true, Ordering::Acquire) {
+                core::hint::spin_loop();
+            }
+        }
+
+        fn try_lock(&self) -> bool {
+            !self.0.swap(true, Ordering::Acquire)
+        }
+
+        unsafe fn unlock(&self) {
+            self.0.store(false, Ordering::Release);
+        }
+
+        fn is_locked(&self) -> bool {
+            self.0.load(Ordering::Relaxed)
+        }
+    }
+
+    type TestMutex = RawInterruptMutex<FakeMutex, TestBackend>;
+
+    #[test]
+    fn nesting_keeps_interrupts_disabled_regardless_of_drop_order() {
+        static A: TestMutex = TestMutex::INIT;
+        static B: TestMutex = TestMutex::INIT;
+
+        assert!(ENABLED.load(Ordering::SeqCst));
+
+        A.lock();
+        assert!(!ENABLED.load(Ordering::SeqCst));
+        B.lock();
+        assert!(!ENABLED.load(Ordering::SeqCst));
+
+        // Drop the outer guard first: interrupts must stay disabled because `B` is still held.
+        // SAFETY: `A` was locked above and is not locked elsewhere.
+        unsafe { A.unlock() };
+        assert!(!ENABLED.load(Ordering::SeqCst));
+        // SAFETY: `B` was locked above and is not locked elsewhere.
+        unsafe { B.unlock() };
+        assert!(ENABLED.load(Ordering::SeqCst));
+
+        A.lock();
+        B.lock();
+        // Drop the inner guard first: interrupts must stay disabled because `A` is still held.
+        // SAFETY: `B` was locked above and is not locked elsewhere.
+        unsafe { B.unlock() };
+        assert!(!ENABLED.load(Ordering::SeqCst));
+        // SAFETY: `A` was locked above and is not locked elsewhere.
+        unsafe { A.unlock() };
+        assert!(ENABLED.load(Ordering::SeqCst));
+    }
+}
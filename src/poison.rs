@@ -0,0 +1,155 @@
+//! An optional poisoning layer for interrupt-shared mutexes.
+//!
+//! Data shared with interrupt handlers is exactly where a panic mid-update is dangerous: the
+//! handler may then observe a half-written invariant. [`PoisonInterruptMutex`] borrows the
+//! poisoning strategy of [`std::sync::Mutex`]: if a guard is dropped while unwinding from a
+//! panic, the lock is marked poisoned, and subsequent lock attempts return a [`PoisonError`]
+//! carrying the guard, so callers can recover deliberately instead of observing that invariant.
+//!
+//! This needs the standard library (to detect unwinding via [`std::thread::panicking`]), so it is
+//! gated behind the `poison` feature, which the core, `no_std` crate does not enable by default.
+
+use core::mem::ManuallyDrop;
+use core::ops::{Deref, DerefMut};
+use core::sync::atomic::{AtomicBool, Ordering};
+
+use lock_api::RawMutex;
+use std::sync::{LockResult, PoisonError, TryLockError, TryLockResult};
+
+use crate::backend::{DefaultBackend, InterruptBackend};
+use crate::mutex::RawInterruptMutex;
+
+/// An [`InterruptMutex`](crate::InterruptMutex) that poisons itself if a panic occurs while a
+/// guard is held, mirroring [`std::sync::Mutex`].
+///
+/// Unlike [`InterruptMutex`](crate::InterruptMutex), [`lock`](Self::lock) and
+/// [`try_lock`](Self::try_lock) return [`LockResult`]/[`TryLockResult`] instead of the guard
+/// directly.
+pub struct PoisonInterruptMutex<I: RawMutex, T: ?Sized, B: InterruptBackend = DefaultBackend> {
+    poisoned: AtomicBool,
+    inner: lock_api::Mutex<RawInterruptMutex<I, B>, T>,
+}
+
+impl<I: RawMutex, T, B: InterruptBackend> PoisonInterruptMutex<I, T, B> {
+    /// Creates a new, unpoisoned mutex holding `value`.
+    pub const fn new(value: T) -> Self {
+        Self {
+            poisoned: AtomicBool::new(false),
+            inner: lock_api::Mutex::const_new(<RawInterruptMutex<I, B> as RawMutex>::INIT, value),
+        }
+    }
+}
+
+impl<I: RawMutex, T: ?Sized, B: InterruptBackend> PoisonInterruptMutex<I, T, B> {
+    /// Acquires the mutex, blocking the current thread until it is able to do so.
+    ///
+    /// If another user of this mutex panicked while holding it, this returns a [`PoisonError`]
+    /// wrapping the acquired guard, rather than the guard itself.
+    pub fn lock(&self) -> LockResult<PoisonInterruptMutexGuard<'_, I, T, B>> {
+        let guard = PoisonInterruptMutexGuard {
+            poisoned: &self.poisoned,
+            guard: ManuallyDrop::new(self.inner.lock()),
+        };
+        if self.is_poisoned() {
+            Err(PoisonError::new(guard))
+        } else {
+            Ok(guard)
+        }
+    }
+
+    /// Attempts to acquire the mutex without blocking.
+    pub fn try_lock(&self) -> TryLockResult<PoisonInterruptMutexGuard<'_, I, T, B>> {
+        let inner = self.inner.try_lock().ok_or(TryLockError::WouldBlock)?;
+        let guard = PoisonInterruptMutexGuard {
+            poisoned: &self.poisoned,
+            guard: ManuallyDrop::new(inner),
+        };
+        if self.is_poisoned() {
+            Err(TryLockError::Poisoned(PoisonError::new(guard)))
+        } else {
+            Ok(guard)
+        }
+    }
+
+    /// Returns whether the mutex is poisoned.
+    pub fn is_poisoned(&self) -> bool {
+        self.poisoned.load(Ordering::Relaxed)
+    }
+
+    /// Clears the poisoned state of the mutex.
+    ///
+    /// Future calls to [`lock`](Self::lock) and [`try_lock`](Self::try_lock) will succeed as
+    /// normal, without returning a [`PoisonError`], as long as it is not poisoned again.
+    pub fn clear_poison(&self) {
+        self.poisoned.store(false, Ordering::Relaxed);
+    }
+}
+
+/// A guard for [`PoisonInterruptMutex`], poisoning the mutex on drop if it is dropped while
+/// unwinding from a panic.
+pub struct PoisonInterruptMutexGuard<
+    'a,
+    I: RawMutex,
+    T: ?Sized,
+    B: InterruptBackend = DefaultBackend,
+> {
+    poisoned: &'a AtomicBool,
+    guard: ManuallyDrop<lock_api::MutexGuard<'a, RawInterruptMutex<I, B>, T>>,
+}
+
+impl<I: RawMutex, T: ?Sized, B: InterruptBackend> Deref for PoisonInterruptMutexGuard<'_, I, T, B> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.guard
+    }
+}
+
+impl<I: RawMutex, T: ?Sized, B: InterruptBackend> DerefMut
+    for PoisonInterruptMutexGuard<'_, I, T, B>
+{
+    fn deref_mut(&mut self) -> &mut T {
+        &mut self.guard
+    }
+}
+
+impl<I: RawMutex, T: ?Sized, B: InterruptBackend> Drop for PoisonInterruptMutexGuard<'_, I, T, B> {
+    fn drop(&mut self) {
+        if std::thread::panicking() {
+            self.poisoned.store(true, Ordering::Relaxed);
+        }
+        // SAFETY: `self.guard` is not accessed again after this.
+        unsafe {
+            ManuallyDrop::drop(&mut self.guard);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::panic::{self, AssertUnwindSafe};
+
+    use super::*;
+
+    type TestMutex<T> = PoisonInterruptMutex<parking_lot::RawMutex, T>;
+
+    #[test]
+    fn panicking_while_locked_poisons_the_mutex() {
+        let mutex = TestMutex::new(0);
+
+        let result = panic::catch_unwind(AssertUnwindSafe(|| {
+            let mut guard = mutex.lock().unwrap();
+            *guard += 1;
+            panic!("oh no");
+        }));
+        assert!(result.is_err());
+
+        assert!(mutex.is_poisoned());
+        assert!(mutex.lock().is_err());
+        assert!(mutex.try_lock().is_err());
+
+        mutex.clear_poison();
+        assert!(!mutex.is_poisoned());
+        assert_eq!(*mutex.lock().unwrap(), 1);
+    }
+}
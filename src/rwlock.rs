@@ -0,0 +1,249 @@
+use core::marker::PhantomData;
+
+use lock_api::{GuardNoSend, RawRwLock};
+
+use crate::backend::{DefaultBackend, InterruptBackend};
+
+/// A reader-writer lock for sharing data with interrupt handlers or signal handlers.
+///
+/// This lock wraps another [`RawRwLock`] and disables interrupts while locked, using `B` to
+/// decide what disabling and restoring interrupts means on the target environment.
+/// [`DefaultBackend`] is used when `B` is left unspecified, disabling interrupts via the
+/// [`interrupts`] crate.
+///
+/// Unlike [`RawInterruptMutex`](crate::RawInterruptMutex), multiple readers can hold the lock at
+/// the same time, so interrupts must stay disabled for as long as *any* reader or writer holds the
+/// lock. This uses the same [`InterruptBackend::with_nesting`] state as
+/// [`RawInterruptMutex`](crate::RawInterruptMutex), so interrupts stay disabled for as long as
+/// *any* interrupt-mutex or interrupt-rwlock sharing backend `B` is held, regardless of the order
+/// their guards are dropped in. Every shared and exclusive acquisition adds one to the nesting
+/// depth, including repeated acquisitions on the same core or thread, and interrupts are only
+/// restored once the depth returns to 0.
+pub struct RawInterruptRwLock<I, B: InterruptBackend = DefaultBackend> {
+    inner: I,
+    _backend: PhantomData<B>,
+}
+
+// SAFETY: Interrupt state is tracked in the backend's `Nesting` (see
+// `InterruptBackend::with_nesting`), not in `self`.
+unsafe impl<I: Sync, B: InterruptBackend> Sync for RawInterruptRwLock<I, B> {}
+// SAFETY: Locks cannot be send to other threads while locked.
+// Sending them while unlocked is fine.
+unsafe impl<I: Send, B: InterruptBackend> Send for RawInterruptRwLock<I, B> {}
+
+unsafe impl<I: RawRwLock, B: InterruptBackend> RawRwLock for RawInterruptRwLock<I, B> {
+    const INIT: Self = Self {
+        inner: I::INIT,
+        _backend: PhantomData,
+    };
+
+    type GuardMarker = GuardNoSend;
+
+    #[inline]
+    fn lock_shared(&self) {
+        B::with_nesting(|n| n.enter::<B>());
+        self.inner.lock_shared();
+    }
+
+    #[inline]
+    fn try_lock_shared(&self) -> bool {
+        B::with_nesting(|n| n.enter::<B>());
+        let ok = self.inner.try_lock_shared();
+        if !ok {
+            B::with_nesting(|n| n.exit());
+        }
+        ok
+    }
+
+    #[inline]
+    unsafe fn unlock_shared(&self) {
+        unsafe {
+            self.inner.unlock_shared();
+        }
+        B::with_nesting(|n| n.exit());
+    }
+
+    #[inline]
+    fn lock_exclusive(&self) {
+        B::with_nesting(|n| n.enter::<B>());
+        self.inner.lock_exclusive();
+    }
+
+    #[inline]
+    fn try_lock_exclusive(&self) -> bool {
+        B::with_nesting(|n| n.enter::<B>());
+        let ok = self.inner.try_lock_exclusive();
+        if !ok {
+            B::with_nesting(|n| n.exit());
+        }
+        ok
+    }
+
+    #[inline]
+    unsafe fn unlock_exclusive(&self) {
+        unsafe {
+            self.inner.unlock_exclusive();
+        }
+        B::with_nesting(|n| n.exit());
+    }
+
+    #[inline]
+    fn is_locked(&self) -> bool {
+        self.inner.is_locked()
+    }
+}
+
+/// A [`lock_api::RwLock`] based on [`RawInterruptRwLock`].
+pub type InterruptRwLock<I, T, B = DefaultBackend> = lock_api::RwLock<RawInterruptRwLock<I, B>, T>;
+
+/// A [`lock_api::RwLockReadGuard`] based on [`RawInterruptRwLock`].
+pub type InterruptRwLockReadGuard<'a, I, T, B = DefaultBackend> =
+    lock_api::RwLockReadGuard<'a, RawInterruptRwLock<I, B>, T>;
+
+/// A [`lock_api::RwLockWriteGuard`] based on [`RawInterruptRwLock`].
+pub type InterruptRwLockWriteGuard<'a, I, T, B = DefaultBackend> =
+    lock_api::RwLockWriteGuard<'a, RawInterruptRwLock<I, B>, T>;
+
+#[cfg(test)]
+mod tests {
+    use core::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+
+    use super::*;
+    use crate::backend::Nesting;
+
+    static ENABLED: AtomicBool = AtomicBool::new(true);
+
+    struct TestBackend;
+
+    struct TestGuard;
+
+    impl Drop for TestGuard {
+        fn drop(&mut self) {
+            ENABLED.store(true, Ordering::SeqCst);
+        }
+    }
+
+    impl InterruptBackend for TestBackend {
+        type Guard = TestGuard;
+
+        fn disable() -> Self::Guard {
+            ENABLED.store(false, Ordering::SeqCst);
+            TestGuard
+        }
+
+        fn with_nesting<R>(f: impl FnOnce(&Nesting<Self::Guard>) -> R) -> R {
+            static NESTING: Nesting<TestGuard> = Nesting::new();
+            f(&NESTING)
+        }
+    }
+
+    struct FakeRwLock(AtomicUsize);
+
+    const WRITER: usize = usize::MAX;
+
+    unsafe impl RawRwLock for FakeRwLock {
+        const INIT: Self = Self(AtomicUsize::new(0));
+
+        type GuardMarker = GuardNoSend;
+
+        fn lock_shared(&self) {
+            while !self.try_lock_shared() {
+                core::hint::spin_loop();
+            }
+        }
+
+        fn try_lock_shared(&self) -> bool {
+            self.0
+                .fetch_update(Ordering::Acquire, Ordering::Relaxed, |readers| {
+                    (readers != WRITER).then_some(readers + 1)
+                })
+                .is_ok()
+        }
+
+        unsafe fn unlock_shared(&self) {
+            self.0.fetch_sub(1, Ordering::Release);
+        }
+
+        fn lock_exclusive(&self) {
+            while !self.try_lock_exclusive() {
+                core::hint::spin_loop();
+            }
+        }
+
+        fn try_lock_exclusive(&self) -> bool {
+            self.0
+                .compare_exchange(0, WRITER, Ordering::Acquire, Ordering::Relaxed)
+                .is_ok()
+        }
+
+        unsafe fn unlock_exclusive(&self) {
+            self.0.store(0, Ordering::Release);
+        }
+
+        fn is_locked(&self) -> bool {
+            self.0.load(Ordering::Relaxed) != 0
+        }
+    }
+
+    type TestRwLock = RawInterruptRwLock<FakeRwLock, TestBackend>;
+
+    #[test]
+    fn interrupts_stay_disabled_until_the_last_reader_releases() {
+        static LOCK: TestRwLock = TestRwLock::INIT;
+
+        assert!(ENABLED.load(Ordering::SeqCst));
+
+        LOCK.lock_shared();
+        assert!(!ENABLED.load(Ordering::SeqCst));
+
+        // A second, concurrent reader must not be the one to restore interrupts.
+        LOCK.lock_shared();
+        assert!(!ENABLED.load(Ordering::SeqCst));
+
+        // SAFETY: `LOCK` was locked twice above and is not locked elsewhere.
+        unsafe { LOCK.unlock_shared() };
+        assert!(!ENABLED.load(Ordering::SeqCst));
+        // SAFETY: `LOCK` still has the other shared lock above and is not locked elsewhere.
+        unsafe { LOCK.unlock_shared() };
+        assert!(ENABLED.load(Ordering::SeqCst));
+
+        LOCK.lock_exclusive();
+        assert!(!ENABLED.load(Ordering::SeqCst));
+        // SAFETY: `LOCK` was locked above and is not locked elsewhere.
+        unsafe { LOCK.unlock_exclusive() };
+        assert!(ENABLED.load(Ordering::SeqCst));
+    }
+
+    #[test]
+    fn nesting_is_shared_across_rwlock_instances_regardless_of_drop_order() {
+        static A: TestRwLock = TestRwLock::INIT;
+        static B: TestRwLock = TestRwLock::INIT;
+
+        assert!(ENABLED.load(Ordering::SeqCst));
+
+        A.lock_shared();
+        assert!(!ENABLED.load(Ordering::SeqCst));
+        B.lock_shared();
+        assert!(!ENABLED.load(Ordering::SeqCst));
+
+        // Drop the first lock's guard first: interrupts must stay disabled because `B` is still
+        // held, even though `A` and `B` are different `RawInterruptRwLock` instances.
+        // SAFETY: `A` was locked above and is not locked elsewhere.
+        unsafe { A.unlock_shared() };
+        assert!(!ENABLED.load(Ordering::SeqCst));
+        // SAFETY: `B` was locked above and is not locked elsewhere.
+        unsafe { B.unlock_shared() };
+        assert!(ENABLED.load(Ordering::SeqCst));
+
+        A.lock_shared();
+        B.lock_shared();
+        // Drop the second lock's guard first: interrupts must stay disabled because `A` is still
+        // held.
+        // SAFETY: `B` was locked above and is not locked elsewhere.
+        unsafe { B.unlock_shared() };
+        assert!(!ENABLED.load(Ordering::SeqCst));
+        // SAFETY: `A` was locked above and is not locked elsewhere.
+        unsafe { A.unlock_shared() };
+        assert!(ENABLED.load(Ordering::SeqCst));
+    }
+}